@@ -1,30 +1,43 @@
-use std::env;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::BufReader;
 use std::path::PathBuf;
-use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 
+use actix::{Actor, ActorContext, ActorFutureExt, Addr, AsyncContext, Context, Handler, Recipient, StreamHandler, WrapFuture};
+use actix_cors::Cors;
 use actix_files::NamedFile;
 use actix_session::{CookieSession, Session};
-use actix_web::{App, get, HttpRequest, HttpResponse, HttpServer, post, put, Result, web};
+use actix_web::{App, delete, get, HttpRequest, HttpResponse, HttpServer, post, put, Result, web};
+use actix_web::http::header;
 use actix_web::middleware::Logger;
+use actix_web_actors::ws;
 use askama::Template;
 use chrono::offset::Utc;
 use env_logger;
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::params;
+use rusqlite::{params, OptionalExtension};
 use rustls::{NoClientAuth, ServerConfig};
 use rustls::internal::pemfile::{certs, rsa_private_keys};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::env;
+
+/// How often the server pings each open /students/ws socket.
+const WS_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+/// How long a socket may go without a pong before it's considered dead and dropped.
+const WS_CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
 
 const HOST: &str = "127.0.0.1";
 // const PORT: u32 = 8088;
 // use different port for HTTPS
 const PORT: u32 = 8443;
 const LAST_STUDENT_POST_SESSION_PARAM: &str = "last_student_post";
+const LOGGED_IN_USER_SESSION_PARAM: &str = "logged_in_user";
+const GUEST_USERNAME: &str = "guest";
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 struct Student {
     id: u32,
     firstname: String,
@@ -32,35 +45,64 @@ struct Student {
     fav_language: String,
 }
 
-impl Student {
-    fn new(id: u32, firstname: &str, lastname: &str, fav_language: &str) -> Self {
-        Self {
-            id,
-            firstname: String::from(firstname),
-            lastname: String::from(lastname),
-            fav_language: String::from(fav_language),
-        }
-    }
+/// Shared application configuration
+struct AppConfig {
+    // sha256 hex digest of the login password; an empty-string hash enables guest mode
+    password_hash: String,
 }
 
-/// Shared application state type
+/// Shared application state: the hub that fans out live student updates to
+/// every open /students/ws socket.
 struct AppState {
-    // Mutex (or RwLock) is necessary to mutate safely across threads
-    teacher_name: Mutex<String>,
-    students: Mutex<Vec<Student>>,
-}
-
-impl AppState {
-    fn find_student(&self, id: u32) -> Option<Student> {
-        let res: Option<Student>;
-        let mutex_guard = self.students.lock().unwrap();
-        for s in mutex_guard.iter() {
-            if s.id == id {
-                res = Some(s.clone());
-                return res;
-            }
-        }
-        None
+    students_hub: Addr<StudentsHub>,
+}
+
+fn hash_password(password: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(password.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Builds the 401 response browsers use to pop up a Basic auth prompt.
+fn unauthorized_response() -> HttpResponse {
+    HttpResponse::Unauthorized()
+        .header(header::WWW_AUTHENTICATE, "Basic realm=\"learn-rust-webapp\"")
+        .finish()
+}
+
+/// Guards a mutating endpoint behind session-backed Basic auth, falling back to a guest
+/// login when no password is configured. Returns the 401 response to send back on failure.
+fn auth(session: &Session, req: &HttpRequest, password_hash: &str) -> std::result::Result<(), HttpResponse> {
+    if session.get::<String>(LOGGED_IN_USER_SESSION_PARAM).unwrap_or(None).is_some() {
+        return Ok(());
+    }
+
+    if password_hash == hash_password("") {
+        session.set(LOGGED_IN_USER_SESSION_PARAM, GUEST_USERNAME).unwrap();
+        return Ok(());
+    }
+
+    let credentials = req.headers().get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Basic "))
+        .and_then(|encoded| base64::decode(encoded).ok())
+        .and_then(|decoded| String::from_utf8(decoded).ok());
+
+    let (username, password) = match credentials.as_ref().and_then(|c| {
+        let mut parts = c.splitn(2, ':');
+        let user = parts.next()?;
+        let pass = parts.next()?;
+        Some((user, pass))
+    }) {
+        Some(pair) => pair,
+        None => return Err(unauthorized_response()),
+    };
+
+    if hash_password(password) == password_hash {
+        session.set(LOGGED_IN_USER_SESSION_PARAM, username).unwrap();
+        Ok(())
+    } else {
+        Err(unauthorized_response())
     }
 }
 
@@ -93,6 +135,26 @@ async fn get_404_page(req: HttpRequest) -> Result<HttpResponse> {
     Ok(HttpResponse::NotFound().content_type("text/html").body(html))
 }
 
+/// True if the caller's `Accept` header asks for JSON rather than HTML, so REST resources
+/// can serve API clients and browser forms from the same endpoint.
+fn wants_json(req: &HttpRequest) -> bool {
+    req.headers()
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .map(|accept| accept.contains("application/json"))
+        .unwrap_or(false)
+}
+
+/// 404 handler that honours content negotiation: JSON for API clients, the `404.html`
+/// template for browsers.
+async fn get_404_response(req: &HttpRequest) -> Result<HttpResponse> {
+    if wants_json(req) {
+        Ok(HttpResponse::NotFound().json(serde_json::json!({ "error": "Not Found" })))
+    } else {
+        get_404_page(req.clone()).await
+    }
+}
+
 /// favicon handler
 /// You can also define routes using macro attributes which allow you to specify the routes above
 /// your functions like so:
@@ -118,9 +180,90 @@ struct StudentsTemplate<'a> {
     last_post: &'a str,
 }
 
+fn db_read_students(db: &Pool<SqliteConnectionManager>) -> Vec<Student> {
+    let conn = db.get().unwrap();
+
+    let mut stmt = conn.prepare("SELECT id, firstname, lastname, fav_language FROM student").expect("Database connection error");
+    let query_result = stmt.query_map(params![], |row| {
+        Ok(Student {
+            id: row.get(0)?,
+            firstname: row.get(1)?,
+            lastname: row.get(2)?,
+            fav_language: row.get(3)?,
+        })
+    });
+    let rows = query_result.unwrap();
+
+    let mut students: Vec<Student> = Vec::new();
+    for student in rows {
+        students.push(student.unwrap());
+    }
+
+    students
+}
+
+fn db_find_student(db: &Pool<SqliteConnectionManager>, id: u32) -> Option<Student> {
+    let conn = db.get().unwrap();
+
+    conn.query_row(
+        "SELECT id, firstname, lastname, fav_language FROM student WHERE id = ?1",
+        params![id],
+        |row| {
+            Ok(Student {
+                id: row.get(0)?,
+                firstname: row.get(1)?,
+                lastname: row.get(2)?,
+                fav_language: row.get(3)?,
+            })
+        },
+    ).optional().expect("Database connection error")
+}
+
+fn db_insert_student(db: &Pool<SqliteConnectionManager>, firstname: &str, lastname: &str, fav_language: &str) -> Student {
+    let conn = db.get().unwrap();
+    conn.execute(
+        "INSERT INTO student (firstname, lastname, fav_language) VALUES (?1, ?2, ?3)",
+        params![firstname, lastname, fav_language],
+    ).expect("Database connection error");
+
+    Student {
+        id: conn.last_insert_rowid() as u32,
+        firstname: firstname.to_string(),
+        lastname: lastname.to_string(),
+        fav_language: fav_language.to_string(),
+    }
+}
+
+/// Updates a student's fields and returns the updated row, or `None` if no student with
+/// `id` existed (e.g. it was concurrently deleted) — so callers never have to assume the
+/// row is still there after a separate existence check.
+fn db_update_student(db: &Pool<SqliteConnectionManager>, id: u32, firstname: &str, lastname: &str, fav_language: &str) -> Option<Student> {
+    let conn = db.get().unwrap();
+    let rows_affected = conn.execute(
+        "UPDATE student SET firstname = ?1, lastname = ?2, fav_language = ?3 WHERE id = ?4",
+        params![firstname, lastname, fav_language, id],
+    ).expect("Database connection error");
+
+    if rows_affected == 0 {
+        None
+    } else {
+        Some(Student {
+            id,
+            firstname: firstname.to_string(),
+            lastname: lastname.to_string(),
+            fav_language: fav_language.to_string(),
+        })
+    }
+}
+
+fn db_delete_student(db: &Pool<SqliteConnectionManager>, id: u32) {
+    let conn = db.get().unwrap();
+    conn.execute("DELETE FROM student WHERE id = ?1", params![id]).expect("Database connection error");
+}
+
 #[get("/students")]
-async fn get_students_page(session: Session, app_state: web::Data<AppState>) -> Result<HttpResponse> {
-    let students = app_state.students.lock().unwrap();
+async fn get_students_page(session: Session, db: web::Data<Pool<SqliteConnectionManager>>) -> Result<HttpResponse> {
+    let students = db_read_students(&db);
 
     let html = StudentsTemplate {
         title: "Students",
@@ -140,26 +283,41 @@ struct NewStudentFormData {
 }
 
 /// Handler to create a new student resource under /students via POST request.
-/// Gets called only if the content type is "application/x-www-form-urlencoded".
-/// and the content of the request could be deserialized to a `TeacherUpdateInfo` struct.
+/// Accepts either a url-encoded form (browser clients) or a JSON body (API clients),
+/// and renders back whichever content type the `Accept` header asked for.
 /// Timestamp of last POST saved to session state (cookie).
 #[post("/students")]
-async fn post_student(form: web::Form<NewStudentFormData>, session: Session,
+async fn post_student(form_or_json: web::Either<web::Form<NewStudentFormData>, web::Json<NewStudentFormData>>,
+                      session: Session, req: HttpRequest,
+                      db: web::Data<Pool<SqliteConnectionManager>>,
+                      config: web::Data<AppConfig>,
                       app_state: web::Data<AppState>) -> Result<HttpResponse> {
-    let mut students = app_state.students.lock().unwrap();
-    let new_student =
-        Student::new(students.len() as u32 + 1, &form.fname, &form.lname, &form.lang);
-    students.push(new_student);
+    if let Err(unauthorized) = auth(&session, &req, &config.password_hash) {
+        return Ok(unauthorized);
+    }
+
+    let form = match form_or_json {
+        web::Either::A(form) => form.into_inner(),
+        web::Either::B(json) => json.into_inner(),
+    };
+
+    let new_student = db_insert_student(&db, &form.fname, &form.lname, &form.lang);
+    app_state.students_hub.do_send(BroadcastStudent(new_student.clone()));
 
     record_student_post_time(&session);
 
-    let html = StudentsTemplate {
-        title: "Students",
-        students: &students[..], // extract slice of all vector elements
-        last_post: &get_last_student_post_time(&session),
-    }.render().unwrap();
+    if wants_json(&req) {
+        Ok(HttpResponse::Ok().json(new_student))
+    } else {
+        let students = db_read_students(&db);
+        let html = StudentsTemplate {
+            title: "Students",
+            students: &students[..], // extract slice of all vector elements
+            last_post: &get_last_student_post_time(&session),
+        }.render().unwrap();
 
-    Ok(HttpResponse::Ok().content_type("text/html").body(html))
+        Ok(HttpResponse::Ok().content_type("text/html").body(html))
+    }
 }
 
 /// Askama template data for Student page
@@ -172,17 +330,11 @@ struct StudentTemplate<'a> {
     fav_language: &'a str,
 }
 
-/// Use Path extractor to extract id segment from /students/{id} into tuple
-#[get("/students/{id}")]
-async fn get_student_page(web::Path((student_id, )): web::Path<(u32, )>,
-                          req: HttpRequest,
-                          app_state: web::Data<AppState>) -> Result<HttpResponse> {
-    let student_option = app_state.find_student(student_id);
-
-    if student_option.is_none() {
-        get_404_page(req).await
+/// Renders a single student as HTML or JSON depending on the `Accept` header.
+fn render_student(req: &HttpRequest, student: &Student) -> Result<HttpResponse> {
+    if wants_json(req) {
+        Ok(HttpResponse::Ok().json(student))
     } else {
-        let student = student_option.unwrap();
         let html = StudentTemplate {
             title: "Student",
             firstname: &student.firstname,
@@ -194,6 +346,239 @@ async fn get_student_page(web::Path((student_id, )): web::Path<(u32, )>,
     }
 }
 
+/// Use Path extractor to extract id segment from /students/{id} into tuple
+#[get("/students/{id}")]
+async fn get_student_page(web::Path((student_id, )): web::Path<(u32, )>,
+                          req: HttpRequest,
+                          db: web::Data<Pool<SqliteConnectionManager>>) -> Result<HttpResponse> {
+    match db_find_student(&db, student_id) {
+        None => get_404_response(&req).await,
+        Some(student) => render_student(&req, &student),
+    }
+}
+
+/// Handler to update an existing student's firstname/lastname/fav_language via PUT request.
+#[put("/students/{id}")]
+async fn put_student(web::Path((student_id, )): web::Path<(u32, )>,
+                     body: web::Json<NewStudentFormData>,
+                     session: Session, req: HttpRequest,
+                     db: web::Data<Pool<SqliteConnectionManager>>,
+                     config: web::Data<AppConfig>) -> Result<HttpResponse> {
+    if let Err(unauthorized) = auth(&session, &req, &config.password_hash) {
+        return Ok(unauthorized);
+    }
+
+    match db_update_student(&db, student_id, &body.fname, &body.lname, &body.lang) {
+        Some(student) => render_student(&req, &student),
+        None => get_404_response(&req).await,
+    }
+}
+
+/// Handler to delete a student resource via DELETE request.
+#[delete("/students/{id}")]
+async fn delete_student(web::Path((student_id, )): web::Path<(u32, )>,
+                        session: Session, req: HttpRequest,
+                        db: web::Data<Pool<SqliteConnectionManager>>,
+                        config: web::Data<AppConfig>,
+                        app_state: web::Data<AppState>) -> Result<HttpResponse> {
+    if let Err(unauthorized) = auth(&session, &req, &config.password_hash) {
+        return Ok(unauthorized);
+    }
+
+    if db_find_student(&db, student_id).is_none() {
+        return get_404_response(&req).await;
+    }
+
+    db_delete_student(&db, student_id);
+    app_state.students_hub.do_send(BroadcastStudentDeleted(student_id));
+
+    if wants_json(&req) {
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        let students = db_read_students(&db);
+        let html = StudentsTemplate {
+            title: "Students",
+            students: &students[..],
+            last_post: &get_last_student_post_time(&session),
+        }.render().unwrap();
+
+        Ok(HttpResponse::Ok().content_type("text/html").body(html))
+    }
+}
+
+/// Message the hub sends down to an individual /students/ws socket.
+#[derive(actix::Message)]
+#[rtype(result = "()")]
+struct WsStudentUpdate(String);
+
+/// Registers a newly opened socket with the hub, which replies with its registry id.
+#[derive(actix::Message)]
+#[rtype(result = "usize")]
+struct Connect {
+    addr: Recipient<WsStudentUpdate>,
+}
+
+/// Deregisters a closed socket from the hub.
+#[derive(actix::Message)]
+#[rtype(result = "()")]
+struct Disconnect {
+    id: usize,
+}
+
+/// Fans a newly created student out to every open socket.
+#[derive(actix::Message)]
+#[rtype(result = "()")]
+struct BroadcastStudent(Student);
+
+/// Fans a student deletion out to every open socket, as a tombstone carrying just the id.
+#[derive(actix::Message)]
+#[rtype(result = "()")]
+struct BroadcastStudentDeleted(u32);
+
+/// Actor that keeps a registry of every open /students/ws socket, so that
+/// `post_student` can push newly created students to all of them.
+struct StudentsHub {
+    sockets: HashMap<usize, Recipient<WsStudentUpdate>>,
+    next_id: usize,
+}
+
+impl StudentsHub {
+    fn new() -> Self {
+        Self { sockets: HashMap::new(), next_id: 0 }
+    }
+}
+
+impl Actor for StudentsHub {
+    type Context = Context<Self>;
+}
+
+impl Handler<Connect> for StudentsHub {
+    type Result = usize;
+
+    fn handle(&mut self, msg: Connect, _ctx: &mut Self::Context) -> Self::Result {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.sockets.insert(id, msg.addr);
+        id
+    }
+}
+
+impl Handler<Disconnect> for StudentsHub {
+    type Result = ();
+
+    fn handle(&mut self, msg: Disconnect, _ctx: &mut Self::Context) {
+        self.sockets.remove(&msg.id);
+    }
+}
+
+impl Handler<BroadcastStudent> for StudentsHub {
+    type Result = ();
+
+    fn handle(&mut self, msg: BroadcastStudent, _ctx: &mut Self::Context) {
+        let json = serde_json::to_string(&msg.0).unwrap_or_default();
+        for socket in self.sockets.values() {
+            socket.do_send(WsStudentUpdate(json.clone()));
+        }
+    }
+}
+
+impl Handler<BroadcastStudentDeleted> for StudentsHub {
+    type Result = ();
+
+    fn handle(&mut self, msg: BroadcastStudentDeleted, _ctx: &mut Self::Context) {
+        let json = serde_json::json!({ "deleted": true, "id": msg.0 }).to_string();
+        for socket in self.sockets.values() {
+            socket.do_send(WsStudentUpdate(json.clone()));
+        }
+    }
+}
+
+/// Per-connection actor backing a single /students/ws socket.
+struct StudentsSocket {
+    id: usize,
+    hb: Instant,
+    hub: Addr<StudentsHub>,
+}
+
+impl StudentsSocket {
+    fn new(hub: Addr<StudentsHub>) -> Self {
+        Self { id: 0, hb: Instant::now(), hub }
+    }
+
+    /// Pings the client on a timer, closing the connection if no pong comes back in time.
+    fn heartbeat(&self, ctx: &mut ws::WebsocketContext<Self>) {
+        ctx.run_interval(WS_HEARTBEAT_INTERVAL, |socket, ctx| {
+            if Instant::now().duration_since(socket.hb) > WS_CLIENT_TIMEOUT {
+                socket.hub.do_send(Disconnect { id: socket.id });
+                ctx.stop();
+                return;
+            }
+            ctx.ping(b"");
+        });
+    }
+}
+
+impl Actor for StudentsSocket {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.heartbeat(ctx);
+
+        let addr = ctx.address().recipient();
+        self.hub
+            .send(Connect { addr })
+            .into_actor(self)
+            .then(|res, socket, ctx| {
+                match res {
+                    Ok(id) => socket.id = id,
+                    Err(_) => ctx.stop(),
+                }
+                actix::fut::ready(())
+            })
+            .wait(ctx);
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        self.hub.do_send(Disconnect { id: self.id });
+    }
+}
+
+impl Handler<WsStudentUpdate> for StudentsSocket {
+    type Result = ();
+
+    fn handle(&mut self, msg: WsStudentUpdate, ctx: &mut Self::Context) {
+        ctx.text(msg.0);
+    }
+}
+
+impl StreamHandler<std::result::Result<ws::Message, ws::ProtocolError>> for StudentsSocket {
+    fn handle(&mut self, item: std::result::Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match item {
+            Ok(ws::Message::Ping(msg)) => {
+                self.hb = Instant::now();
+                ctx.pong(&msg);
+            }
+            Ok(ws::Message::Pong(_)) => {
+                self.hb = Instant::now();
+            }
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            // This is a push-only feed; other client frames are ignored.
+            Ok(_) => {}
+            Err(_) => ctx.stop(),
+        }
+    }
+}
+
+/// WebSocket endpoint that pushes newly created students to open Students page sessions.
+#[get("/students/ws")]
+async fn get_students_ws(req: HttpRequest, stream: web::Payload,
+                         app_state: web::Data<AppState>) -> Result<HttpResponse> {
+    ws::start(StudentsSocket::new(app_state.students_hub.clone()), &req, stream)
+}
+
 // JSON serialization using serde
 #[derive(Serialize)]
 struct Classroom {
@@ -235,13 +620,24 @@ struct TeacherTemplate<'a> {
     name: &'a str,
 }
 
+fn db_read_teacher_name(db: &Pool<SqliteConnectionManager>) -> String {
+    let conn = db.get().unwrap();
+    conn.query_row("SELECT name FROM teacher LIMIT 1", params![], |row| row.get(0))
+        .expect("Database connection error")
+}
+
+fn db_update_teacher_name(db: &Pool<SqliteConnectionManager>, name: &str) {
+    let conn = db.get().unwrap();
+    conn.execute("UPDATE teacher SET name = ?1", params![name]).expect("Database connection error");
+}
+
 #[get("/teacher")]
-async fn get_teacher_page(app_state: web::Data<AppState>) -> Result<HttpResponse> {
-    let teacher_name: MutexGuard<String> = app_state.teacher_name.lock().unwrap();
+async fn get_teacher_page(db: web::Data<Pool<SqliteConnectionManager>>) -> Result<HttpResponse> {
+    let teacher_name = db_read_teacher_name(&db);
 
     let html = TeacherTemplate {
         title: "Teacher",
-        name: &teacher_name.to_string(),
+        name: &teacher_name,
     }.render().unwrap();
 
     Ok(HttpResponse::Ok().content_type("text/html").body(html))
@@ -253,15 +649,19 @@ struct TeacherUpdateInfo {
     name: String,
 }
 
-/// Handler to update the teacher name stored in global application state via PUT request.
+/// Handler to update the teacher name stored in the database via PUT request.
 /// Teacher name specified via JSON in request body (web::Json extractor).
 #[put("/teacher")]
-async fn put_teacher_via_json_req_body(json_body: web::Json<TeacherUpdateInfo>,
-                                       app_state: web::Data<AppState>) -> Result<HttpResponse> {
-    let mut teacher_name: MutexGuard<String> = app_state.teacher_name.lock().unwrap();
-    let previous_name: String = teacher_name.to_string().clone();
-    *teacher_name = json_body.name.clone();
-    let resp_body: String = format!("Teacher changed from '{}' to '{}'", previous_name, teacher_name);
+async fn put_teacher_via_json_req_body(json_body: web::Json<TeacherUpdateInfo>, session: Session, req: HttpRequest,
+                                       db: web::Data<Pool<SqliteConnectionManager>>,
+                                       config: web::Data<AppConfig>) -> Result<HttpResponse> {
+    if let Err(unauthorized) = auth(&session, &req, &config.password_hash) {
+        return Ok(unauthorized);
+    }
+
+    let previous_name = db_read_teacher_name(&db);
+    db_update_teacher_name(&db, &json_body.name);
+    let resp_body: String = format!("Teacher changed from '{}' to '{}'", previous_name, json_body.name);
     Ok(HttpResponse::Ok().content_type("text/plain").body(resp_body))
 }
 
@@ -285,6 +685,28 @@ fn build_ssl_server_config() -> ServerConfig {
     server_config
 }
 
+/// Builds the CORS middleware for the `/classrooms` JSON API.
+///
+/// When `allowed_origins` is empty (nothing configured), any origin is allowed, which
+/// keeps local development friction-free. Otherwise only the configured origins are
+/// allowed, and actix-cors echoes back the single matching origin rather than `*`.
+fn build_cors_middleware(allowed_origins: &[String]) -> Cors {
+    let mut cors = Cors::new()
+        .allowed_methods(vec!["GET", "OPTIONS"])
+        .allowed_headers(vec![header::ACCEPT, header::CONTENT_TYPE])
+        .max_age(3600);
+
+    if allowed_origins.is_empty() {
+        cors = cors.allow_any_origin();
+    } else {
+        for origin in allowed_origins {
+            cors = cors.allowed_origin(origin);
+        }
+    }
+
+    cors.finish()
+}
+
 fn db_create_schema(db: &Pool<SqliteConnectionManager>) {
     let conn = db.get().unwrap();
     conn.execute(
@@ -295,6 +717,24 @@ fn db_create_schema(db: &Pool<SqliteConnectionManager>) {
                   )",
         params![],
     ).expect("Database connection error");
+
+    conn.execute(
+        "CREATE TABLE student (
+                  id              INTEGER PRIMARY KEY,
+                  firstname       TEXT NOT NULL,
+                  lastname        TEXT NOT NULL,
+                  fav_language    TEXT NOT NULL
+                  )",
+        params![],
+    ).expect("Database connection error");
+
+    conn.execute(
+        "CREATE TABLE teacher (
+                  id              INTEGER PRIMARY KEY,
+                  name            TEXT NOT NULL
+                  )",
+        params![],
+    ).expect("Database connection error");
 }
 
 fn db_insert_classroom(db: &Pool<SqliteConnectionManager>, name: &str, capacity: u32) {
@@ -305,16 +745,34 @@ fn db_insert_classroom(db: &Pool<SqliteConnectionManager>, name: &str, capacity:
     ).expect("Database connection error");
 }
 
+fn db_insert_teacher(db: &Pool<SqliteConnectionManager>, name: &str) {
+    let conn = db.get().unwrap();
+    conn.execute(
+        "INSERT INTO teacher (name) VALUES (?1)",
+        params![name],
+    ).expect("Database connection error");
+}
+
 fn init_database() -> Pool<SqliteConnectionManager> {
     // let db_conn_manager: SqliteConnectionManager = SqliteConnectionManager::file("school.db");
     // use in-memory DB for simplicity
     let db_conn_manager: SqliteConnectionManager = SqliteConnectionManager::memory();
-    let db_conn_pool: Pool<SqliteConnectionManager> = r2d2::Pool::new(db_conn_manager).unwrap();
+    // SqliteConnectionManager::memory() gives every pooled connection its own private
+    // database, and r2d2 defaults min_idle to max_size — so without pinning the pool to
+    // a single connection, most connections would never see the schema created below.
+    let db_conn_pool: Pool<SqliteConnectionManager> = r2d2::Pool::builder()
+        .max_size(1)
+        .build(db_conn_manager)
+        .unwrap();
 
     // since we're using an in-memory DB, we have to seed it with some values
     db_create_schema(&db_conn_pool);
     db_insert_classroom(&db_conn_pool, "5VR", 35);
     db_insert_classroom(&db_conn_pool, "2GK", 38);
+    db_insert_teacher(&db_conn_pool, "Louise");
+    db_insert_student(&db_conn_pool, "Claire", "Johnston", "C++");
+    db_insert_student(&db_conn_pool, "David", "Johnston", "Java");
+    db_insert_student(&db_conn_pool, "Mark", "Wong", "Rust");
 
     db_conn_pool
 }
@@ -327,18 +785,45 @@ async fn main() -> std::io::Result<()> {
     env::set_var("RUST_LOG", "actix_web=debug,actix_server=info");
     env_logger::init();
 
-    // Initialize in-memory application state. Do not use in a clustered set-up!
-    let app_state = AppState {
-        teacher_name: Mutex::new(String::from("Louise")),
-        students: Mutex::new(vec![Student::new(1, "Claire", "Johnston", "C++"),
-                                  Student::new(2, "David", "Johnston", "Java"),
-                                  Student::new(3, "Mark", "Wong", "Rust")]),
-    };
-
-    let app_state_extractor = web::Data::new(app_state);
+    // Origins allowed to call the /classrooms JSON API cross-origin, comma-separated.
+    // Left unset (empty), any origin is allowed.
+    let cors_allowed_origins: Vec<String> = env::var("CORS_ALLOWED_ORIGINS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|origin| origin.trim().to_string())
+        .filter(|origin| !origin.is_empty())
+        .collect();
+
+    // Initialize the connection pool. All application state (students, teacher, classrooms)
+    // is stored through this pool and shared by all workers; the database is in-memory, so
+    // it lives in this process only and is not shared with other instances.
     let db_conn_pool: Pool<SqliteConnectionManager> = init_database();
     let server_config = build_ssl_server_config();
 
+    // Login password for the mutating endpoints. An unset PASSWORD_HASH falls back to the
+    // hash of the empty string, which puts the app in guest mode (see `auth`).
+    let password_hash = env::var("PASSWORD_HASH").unwrap_or_else(|_| hash_password(""));
+    let app_config = web::Data::new(AppConfig { password_hash });
+
+    // Time allowed for a client to send the full request head, and to shut down cleanly
+    // after the last response; protects against slowloris-style connection stalls.
+    let client_timeout_millis: u64 = env::var("CLIENT_TIMEOUT_MILLIS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(5_000);
+    let client_shutdown_millis: u64 = env::var("CLIENT_SHUTDOWN_MILLIS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(5_000);
+    let keep_alive_secs: usize = env::var("KEEP_ALIVE_SECS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(75);
+
+    // Start the hub actor that fans live student updates out to open /students/ws sockets.
+    let students_hub = StudentsHub::new().start();
+    let app_state = web::Data::new(AppState { students_hub });
+
     let server = HttpServer::new(move || {
         // "move closure" needed to transfer ownership of values from main thread
         App::new()
@@ -346,20 +831,35 @@ async fn main() -> std::io::Result<()> {
             .wrap(CookieSession::signed(&[0; 32]).secure(false))
             // enable logger - always register actix-web Logger middleware last
             .wrap(Logger::default())
-            // register app_state
-            .app_data(app_state_extractor.clone())
+            // register the database connection pool
             .data(db_conn_pool.clone())
+            // register app config (login password hash)
+            .app_data(app_config.clone())
+            // register app state (websocket hub)
+            .app_data(app_state.clone())
             // register request handlers on a path with a method
             .route("/", web::get().to(get_homepage))
             // simpler registration when using macros
             .service(get_favicon_file)
             .service(get_students_page)
+            // literal /students/ws must be registered before the dynamic /students/{id}
+            // routes below, or actix-web matches {id}="ws" first and never reaches it
+            .service(get_students_ws)
             .service(get_student_page)
             .service(post_student)
-            .service(get_classrooms_json)
+            .service(put_student)
+            .service(delete_student)
             .service(get_teacher_page)
             .service(put_teacher_via_json_req_body)
             .service(serve_static_file)
+            // CORS wraps only this scope, which serves just the /classrooms JSON API; its empty
+            // prefix matches every path, so it is registered after the other routes to keep it
+            // from shadowing them
+            .service(
+                web::scope("")
+                    .wrap(build_cors_middleware(&cors_allowed_origins))
+                    .service(get_classrooms_json),
+            )
             // default
             .default_service(
                 // 404 for GET request
@@ -375,6 +875,11 @@ async fn main() -> std::io::Result<()> {
         // Once the workers are created, they each receive a separate application instance to handle requests.
         // Each worker thread processes its requests sequentially.
         .workers(4)
+        // reject connections that don't finish sending their request head in time (408)
+        .client_timeout(client_timeout_millis)
+        // time allowed for a client to shut down the connection after the response is sent
+        .client_shutdown(client_shutdown_millis)
+        .keep_alive(keep_alive_secs)
         .run();
 
     server.await
@@ -389,6 +894,94 @@ mod tests {
     use super::*;
 
     // Unit tests (test individual request handler functions)
+    #[test]
+    fn unit_test_student_db_functions_round_trip() {
+        let db_conn_pool = init_database();
+
+        let inserted = db_insert_student(&db_conn_pool, "Ada", "Lovelace", "Rust");
+        let found = db_find_student(&db_conn_pool, inserted.id).expect("student should be found");
+        assert_eq!(found.firstname, "Ada");
+        assert_eq!(found.lastname, "Lovelace");
+        assert_eq!(found.fav_language, "Rust");
+
+        let students = db_read_students(&db_conn_pool);
+        assert!(students.iter().any(|s| s.id == inserted.id));
+
+        assert!(db_find_student(&db_conn_pool, inserted.id + 1_000).is_none());
+    }
+
+    #[test]
+    fn unit_test_teacher_db_functions_round_trip() {
+        let db_conn_pool = init_database();
+
+        assert_eq!(db_read_teacher_name(&db_conn_pool), "Louise");
+
+        db_update_teacher_name(&db_conn_pool, "Grace");
+        assert_eq!(db_read_teacher_name(&db_conn_pool), "Grace");
+    }
+
+    /// Stand-in for a `/students/ws` socket actor, recording every `WsStudentUpdate` it gets.
+    struct TestSocket {
+        received: Vec<String>,
+    }
+
+    impl Actor for TestSocket {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<WsStudentUpdate> for TestSocket {
+        type Result = ();
+
+        fn handle(&mut self, msg: WsStudentUpdate, _ctx: &mut Self::Context) {
+            self.received.push(msg.0);
+        }
+    }
+
+    #[derive(actix::Message)]
+    #[rtype(result = "Vec<String>")]
+    struct GetReceived;
+
+    impl Handler<GetReceived> for TestSocket {
+        type Result = Vec<String>;
+
+        fn handle(&mut self, _msg: GetReceived, _ctx: &mut Self::Context) -> Vec<String> {
+            self.received.clone()
+        }
+    }
+
+    #[actix_rt::test]
+    async fn unit_test_students_hub_connect_broadcast_disconnect() {
+        let hub = StudentsHub::new().start();
+        let socket = TestSocket { received: Vec::new() }.start();
+
+        let id = hub.send(Connect { addr: socket.recipient() }).await.unwrap();
+
+        hub.send(BroadcastStudent(Student {
+            id: 1,
+            firstname: "Ada".to_string(),
+            lastname: "Lovelace".to_string(),
+            fav_language: "Rust".to_string(),
+        })).await.unwrap();
+        let after_create = socket.send(GetReceived).await.unwrap();
+        assert_eq!(after_create.len(), 1);
+        assert!(after_create[0].contains("Lovelace"));
+
+        hub.send(BroadcastStudentDeleted(1)).await.unwrap();
+        let after_delete = socket.send(GetReceived).await.unwrap();
+        assert_eq!(after_delete.len(), 2);
+        assert!(after_delete[1].contains("\"deleted\":true"));
+
+        hub.send(Disconnect { id }).await.unwrap();
+        hub.send(BroadcastStudent(Student {
+            id: 2,
+            firstname: "Grace".to_string(),
+            lastname: "Hopper".to_string(),
+            fav_language: "COBOL".to_string(),
+        })).await.unwrap();
+        let after_disconnect = socket.send(GetReceived).await.unwrap();
+        assert_eq!(after_disconnect.len(), 2); // nothing delivered once disconnected
+    }
+
     #[actix_rt::test]
     async fn unit_test_homepage_contents() {
         let resp: Response = get_homepage().await.unwrap();
@@ -419,8 +1012,198 @@ mod tests {
         assert!(body.contains("Back to home"));
     }
 
+    #[test]
+    fn unit_test_hash_password_is_deterministic() {
+        assert_eq!(hash_password("secret"), hash_password("secret"));
+        assert_ne!(hash_password("secret"), hash_password("wrong"));
+    }
+
     // Integration tests (run the application with specific request handlers in a real HTTP server)
 
+    fn basic_auth_header(username: &str, password: &str) -> String {
+        format!("Basic {}", base64::encode(format!("{}:{}", username, password)))
+    }
+
+    fn test_app_state() -> web::Data<AppState> {
+        web::Data::new(AppState { students_hub: StudentsHub::new().start() })
+    }
+
+    #[actix_rt::test]
+    async fn integration_post_student_guest_mode_succeeds_without_credentials() {
+        let config = web::Data::new(AppConfig { password_hash: hash_password("") });
+        let db_conn_pool = init_database();
+        let mut app = test::init_service(
+            App::new()
+                .wrap(CookieSession::signed(&[0; 32]).secure(false))
+                .data(db_conn_pool)
+                .app_data(config)
+                .app_data(test_app_state())
+                .service(post_student),
+        ).await;
+
+        let req: Request = test::TestRequest::post()
+            .uri("/students")
+            .header("content-type", "application/x-www-form-urlencoded")
+            .set_payload("fname=Ada&lname=Lovelace&lang=Rust")
+            .to_request();
+
+        let service_resp = test::call_service(&mut app, req).await;
+        assert!(service_resp.status().is_success());
+    }
+
+    #[actix_rt::test]
+    async fn integration_post_student_wrong_password_is_rejected() {
+        let config = web::Data::new(AppConfig { password_hash: hash_password("correct-horse") });
+        let db_conn_pool = init_database();
+        let mut app = test::init_service(
+            App::new()
+                .wrap(CookieSession::signed(&[0; 32]).secure(false))
+                .data(db_conn_pool)
+                .app_data(config)
+                .app_data(test_app_state())
+                .service(post_student),
+        ).await;
+
+        let req: Request = test::TestRequest::post()
+            .uri("/students")
+            .header("content-type", "application/x-www-form-urlencoded")
+            .header("Authorization", basic_auth_header("teacher", "wrong-password"))
+            .set_payload("fname=Ada&lname=Lovelace&lang=Rust")
+            .to_request();
+
+        let service_resp = test::call_service(&mut app, req).await;
+        assert_eq!(service_resp.status(), http::StatusCode::UNAUTHORIZED);
+        assert!(service_resp.headers().contains_key("WWW-Authenticate"));
+    }
+
+    #[actix_rt::test]
+    async fn integration_post_student_valid_credentials_are_accepted() {
+        let config = web::Data::new(AppConfig { password_hash: hash_password("correct-horse") });
+        let db_conn_pool = init_database();
+        let mut app = test::init_service(
+            App::new()
+                .wrap(CookieSession::signed(&[0; 32]).secure(false))
+                .data(db_conn_pool)
+                .app_data(config)
+                .app_data(test_app_state())
+                .service(post_student),
+        ).await;
+
+        let req: Request = test::TestRequest::post()
+            .uri("/students")
+            .header("content-type", "application/x-www-form-urlencoded")
+            .header("Authorization", basic_auth_header("teacher", "correct-horse"))
+            .set_payload("fname=Ada&lname=Lovelace&lang=Rust")
+            .to_request();
+
+        let service_resp = test::call_service(&mut app, req).await;
+        assert!(service_resp.status().is_success());
+    }
+
+    #[actix_rt::test]
+    async fn integration_post_student_json_body_gets_json_response() {
+        let config = web::Data::new(AppConfig { password_hash: hash_password("") });
+        let db_conn_pool = init_database();
+        let mut app = test::init_service(
+            App::new()
+                .wrap(CookieSession::signed(&[0; 32]).secure(false))
+                .data(db_conn_pool)
+                .app_data(config)
+                .app_data(test_app_state())
+                .service(post_student),
+        ).await;
+
+        let req: Request = test::TestRequest::post()
+            .uri("/students")
+            .header("content-type", "application/json")
+            .header("Accept", "application/json")
+            .set_payload(r#"{"fname":"Ada","lname":"Lovelace","lang":"Rust"}"#)
+            .to_request();
+
+        let service_resp = test::call_service(&mut app, req).await;
+        assert!(service_resp.status().is_success());
+
+        let body: String = get_response_body(service_resp.response());
+        assert!(body.contains("Lovelace"));
+    }
+
+    #[actix_rt::test]
+    async fn integration_put_student_updates_existing_student() {
+        let config = web::Data::new(AppConfig { password_hash: hash_password("") });
+        let db_conn_pool = init_database();
+        let mut app = test::init_service(
+            App::new()
+                .wrap(CookieSession::signed(&[0; 32]).secure(false))
+                .data(db_conn_pool)
+                .app_data(config)
+                .service(put_student),
+        ).await;
+
+        let req: Request = test::TestRequest::put()
+            .uri("/students/1")
+            .header("content-type", "application/json")
+            .header("Accept", "application/json")
+            .set_payload(r#"{"fname":"Claire","lname":"Johnston","lang":"Rust"}"#)
+            .to_request();
+
+        let service_resp = test::call_service(&mut app, req).await;
+        assert!(service_resp.status().is_success());
+
+        let body: String = get_response_body(service_resp.response());
+        assert!(body.contains("Rust"));
+    }
+
+    #[actix_rt::test]
+    async fn integration_put_student_unknown_id_returns_404() {
+        let config = web::Data::new(AppConfig { password_hash: hash_password("") });
+        let db_conn_pool = init_database();
+        let mut app = test::init_service(
+            App::new()
+                .wrap(CookieSession::signed(&[0; 32]).secure(false))
+                .data(db_conn_pool)
+                .app_data(config)
+                .service(put_student),
+        ).await;
+
+        let req: Request = test::TestRequest::put()
+            .uri("/students/999")
+            .header("content-type", "application/json")
+            .header("Accept", "application/json")
+            .set_payload(r#"{"fname":"Claire","lname":"Johnston","lang":"Rust"}"#)
+            .to_request();
+
+        let service_resp = test::call_service(&mut app, req).await;
+        assert_eq!(service_resp.status(), http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn integration_delete_student_removes_existing_student() {
+        let config = web::Data::new(AppConfig { password_hash: hash_password("") });
+        let db_conn_pool = init_database();
+        let mut app = test::init_service(
+            App::new()
+                .wrap(CookieSession::signed(&[0; 32]).secure(false))
+                .data(db_conn_pool)
+                .app_data(config)
+                .app_data(test_app_state())
+                .service(delete_student)
+                .service(get_student_page),
+        ).await;
+
+        let delete_req: Request = test::TestRequest::delete()
+            .uri("/students/1")
+            .header("Accept", "application/json")
+            .to_request();
+        let delete_resp = test::call_service(&mut app, delete_req).await;
+        assert_eq!(delete_resp.status(), http::StatusCode::NO_CONTENT);
+
+        let get_req: Request = test::TestRequest::with_uri("/students/1")
+            .header("Accept", "application/json")
+            .to_request();
+        let get_resp = test::call_service(&mut app, get_req).await;
+        assert_eq!(get_resp.status(), http::StatusCode::NOT_FOUND);
+    }
+
     #[actix_rt::test]
     async fn integration_can_get_homepage() {
         let mut app =
@@ -446,4 +1229,71 @@ mod tests {
         let service_resp = test::call_service(&mut app, req).await;
         assert!(service_resp.status().is_client_error());
     }
+
+    #[actix_rt::test]
+    async fn integration_slow_request_head_times_out_with_408() {
+        use std::io::{Read, Write};
+        use std::net::TcpStream;
+        use std::time::Duration;
+
+        let srv = test::start_with(
+            test::TestServerConfig::default().client_timeout(200),
+            || App::new().route("/", web::get().to(get_homepage)),
+        );
+
+        let mut stream = TcpStream::connect(srv.addr()).unwrap();
+        // Send a partial request head and never send the blank line that terminates
+        // the header block, simulating a slowloris-style client.
+        stream.write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n").unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).ok();
+
+        assert!(response.contains("408"));
+    }
+
+    #[actix_rt::test]
+    async fn integration_students_ws_route_is_reachable_with_full_route_set() {
+        use std::io::{Read, Write};
+        use std::net::TcpStream;
+        use std::time::Duration;
+
+        // Registers the same /students/* services in the same order as main(), so a
+        // regression that shadows /students/ws behind /students/{id} shows up here too.
+        let config = web::Data::new(AppConfig { password_hash: hash_password("") });
+        let app_state = test_app_state();
+        let db_conn_pool = init_database();
+
+        let srv = test::start(move || {
+            App::new()
+                .data(db_conn_pool.clone())
+                .app_data(config.clone())
+                .app_data(app_state.clone())
+                .service(get_students_page)
+                .service(get_students_ws)
+                .service(get_student_page)
+                .service(post_student)
+                .service(put_student)
+                .service(delete_student)
+        });
+
+        let mut stream = TcpStream::connect(srv.addr()).unwrap();
+        stream.write_all(
+            b"GET /students/ws HTTP/1.1\r\n\
+              Host: localhost\r\n\
+              Connection: Upgrade\r\n\
+              Upgrade: websocket\r\n\
+              Sec-WebSocket-Version: 13\r\n\
+              Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+              \r\n",
+        ).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+        let mut buf = [0u8; 512];
+        let n = stream.read(&mut buf).unwrap();
+        let response = String::from_utf8_lossy(&buf[..n]).to_string();
+
+        assert!(response.starts_with("HTTP/1.1 101"), "expected a websocket upgrade, got: {}", response);
+    }
 }